@@ -0,0 +1,194 @@
+use crate::{RamDisk, RamDiskError};
+
+/// A block-addressable storage device: the common interface shared by [`RamDisk`] and,
+/// eventually, other backing stores (flash, disk images, and the like).
+///
+/// Implementors only need to support whole-block reads and writes; [`DiskCursor`]
+/// builds byte-granular access on top of this.
+pub trait BlockDevice {
+    type Error;
+
+    /// The number of addressable blocks on this device.
+    fn num_blocks(&self) -> usize;
+
+    /// The size in bytes of a single block.
+    fn block_size(&self) -> usize;
+
+    /// Reads block `block` into `buffer`, which must be exactly [`BlockDevice::block_size`] bytes.
+    fn read(&self, block: usize, buffer: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Writes `buffer`, which must be exactly [`BlockDevice::block_size`] bytes, to block `block`.
+    fn write(&mut self, block: usize, buffer: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl<const BLOCK_SIZE: usize, const NUM_BLOCKS: usize> BlockDevice
+    for RamDisk<BLOCK_SIZE, NUM_BLOCKS>
+{
+    type Error = RamDiskError;
+
+    fn num_blocks(&self) -> usize {
+        RamDisk::num_blocks(self)
+    }
+
+    fn block_size(&self) -> usize {
+        RamDisk::block_size(self)
+    }
+
+    fn read(&self, block: usize, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let mut block_buffer = [0; BLOCK_SIZE];
+        RamDisk::read(self, block, &mut block_buffer)?;
+        buffer[..BLOCK_SIZE].copy_from_slice(&block_buffer);
+        Ok(())
+    }
+
+    fn write(&mut self, block: usize, buffer: &[u8]) -> Result<(), Self::Error> {
+        let mut block_buffer = [0; BLOCK_SIZE];
+        block_buffer.copy_from_slice(&buffer[..BLOCK_SIZE]);
+        RamDisk::write(self, block, &block_buffer)
+    }
+}
+
+/// A cursor that exposes byte-granular, seekable access to a [`BlockDevice`] by reading
+/// the block a position falls in, patching the relevant bytes, and writing it back.
+///
+/// `BLOCK_SIZE` must match the device's own block size; it is a const generic, like the
+/// rest of this crate, so the cursor's scratch block buffer can live on the stack.
+/// This lets `no_std` filesystem crates that expect a byte stream (for example ones
+/// built against the `core_io` traits) mount a block device such as [`RamDisk`]
+/// directly, without reimplementing block-to-byte translation themselves.
+pub struct DiskCursor<const BLOCK_SIZE: usize, D> {
+    device: D,
+    position: u64,
+}
+
+impl<const BLOCK_SIZE: usize, D: BlockDevice> DiskCursor<BLOCK_SIZE, D> {
+    pub fn new(device: D) -> Self {
+        Self {
+            device,
+            position: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn block_and_offset(&self, position: u64) -> (usize, usize) {
+        (
+            (position / BLOCK_SIZE as u64) as usize,
+            (position % BLOCK_SIZE as u64) as usize,
+        )
+    }
+
+    /// Reads up to `buffer.len()` bytes starting at the cursor's current position,
+    /// returning the number of bytes actually read.
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize, D::Error> {
+        let total_bytes = (self.device.num_blocks() * BLOCK_SIZE) as u64;
+        let mut read = 0;
+        let mut block_buffer = [0; BLOCK_SIZE];
+        while read < buffer.len() && self.position < total_bytes {
+            let (block, offset) = self.block_and_offset(self.position);
+            self.device.read(block, &mut block_buffer)?;
+            let available = BLOCK_SIZE - offset;
+            let to_copy = available.min(buffer.len() - read);
+            buffer[read..read + to_copy].copy_from_slice(&block_buffer[offset..offset + to_copy]);
+            read += to_copy;
+            self.position += to_copy as u64;
+        }
+        Ok(read)
+    }
+
+    /// Writes all of `buffer` starting at the cursor's current position, read-modify-writing
+    /// each block it touches.
+    pub fn write(&mut self, buffer: &[u8]) -> Result<usize, D::Error> {
+        let mut written = 0;
+        let mut block_buffer = [0; BLOCK_SIZE];
+        while written < buffer.len() {
+            let (block, offset) = self.block_and_offset(self.position);
+            self.device.read(block, &mut block_buffer)?;
+            let available = BLOCK_SIZE - offset;
+            let to_copy = available.min(buffer.len() - written);
+            block_buffer[offset..offset + to_copy]
+                .copy_from_slice(&buffer[written..written + to_copy]);
+            self.device.write(block, &block_buffer)?;
+            written += to_copy;
+            self.position += to_copy as u64;
+        }
+        Ok(written)
+    }
+
+    pub fn seek(&mut self, position: u64) {
+        self.position = position;
+    }
+}
+
+#[cfg(feature = "core_io")]
+mod core_io_impl {
+    use super::DiskCursor;
+    use crate::BlockDevice;
+    use core_io::{Error as IoError, ErrorKind, Read, Result as IoResult, Seek, SeekFrom, Write};
+
+    impl<const BLOCK_SIZE: usize, D: BlockDevice> Read for DiskCursor<BLOCK_SIZE, D> {
+        fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+            DiskCursor::read(self, buf).map_err(|_| IoError::from(ErrorKind::Other))
+        }
+    }
+
+    impl<const BLOCK_SIZE: usize, D: BlockDevice> Write for DiskCursor<BLOCK_SIZE, D> {
+        fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+            DiskCursor::write(self, buf).map_err(|_| IoError::from(ErrorKind::Other))
+        }
+
+        fn flush(&mut self) -> IoResult<()> {
+            Ok(())
+        }
+    }
+
+    impl<const BLOCK_SIZE: usize, D: BlockDevice> Seek for DiskCursor<BLOCK_SIZE, D> {
+        fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+            let total_bytes = (self.device.num_blocks() * BLOCK_SIZE) as u64;
+            let new_position = match pos {
+                SeekFrom::Start(offset) => offset,
+                SeekFrom::End(offset) => (total_bytes as i64 + offset) as u64,
+                SeekFrom::Current(offset) => (self.position() as i64 + offset) as u64,
+            };
+            DiskCursor::seek(self, new_position);
+            Ok(new_position)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RamDisk;
+
+    #[test]
+    fn byte_level_read_write_crosses_blocks() {
+        let disk = RamDisk::<4, 4>::new();
+        let mut cursor = DiskCursor::<4, _>::new(disk);
+        cursor.write(b"Hello, cursor!!!").unwrap();
+        cursor.seek(0);
+        let mut buf = [0; 16];
+        let read = cursor.read(&mut buf).unwrap();
+        assert_eq!(read, 16);
+        assert_eq!(&buf, b"Hello, cursor!!!");
+    }
+
+    #[test]
+    fn partial_write_patches_only_its_bytes() {
+        let disk = RamDisk::<4, 4>::new();
+        let mut cursor = DiskCursor::<4, _>::new(disk);
+        cursor.write(b"AAAAAAAAAAAAAAAA").unwrap();
+        cursor.seek(5);
+        cursor.write(b"BB").unwrap();
+        cursor.seek(0);
+        let mut buf = [0; 16];
+        cursor.read(&mut buf).unwrap();
+        assert_eq!(&buf, b"AAAAABBAAAAAAAAA");
+    }
+}
@@ -0,0 +1,18 @@
+//! A small, dependency-free CRC-32 (IEEE 802.3, the one used by zip/gzip/GPT)
+//! implementation shared by every on-disk format in this crate that needs to validate
+//! or stamp a checksum.
+
+pub(crate) fn update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    crc
+}
+
+pub(crate) fn compute(data: &[u8]) -> u32 {
+    !update(0xFFFF_FFFF, data)
+}
@@ -0,0 +1,443 @@
+use crate::{RamDisk, RamDiskError};
+use thiserror_no_std::Error;
+
+/// Maximum length in bytes of a file name stored in the root directory.
+pub const MAX_NAME_LEN: usize = 16;
+
+const INODE_USED_OFFSET: usize = 0;
+const INODE_LEN_OFFSET: usize = 1;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Error)]
+pub enum FileSystemError {
+    #[error("No file named {0:?} exists")]
+    FileNotFound([u8; MAX_NAME_LEN]),
+    #[error("A file named {0:?} already exists")]
+    FileAlreadyExists([u8; MAX_NAME_LEN]),
+    #[error("Too many files are already open")]
+    TooManyOpenFiles,
+    #[error("The disk has no free blocks remaining")]
+    DiskFull,
+    #[error("The root directory has no free inodes remaining")]
+    TooManyFiles,
+    #[error("A file cannot exceed {0} blocks")]
+    FileTooBig(usize),
+    #[error("File descriptor {0} is not open")]
+    NotOpen(usize),
+    #[error("File descriptor {0} was not opened for appending")]
+    NotOpenForAppend(usize),
+    #[error("{0}")]
+    Disk(RamDiskError),
+}
+
+impl core::error::Error for FileSystemError {}
+
+impl From<RamDiskError> for FileSystemError {
+    fn from(value: RamDiskError) -> Self {
+        Self::Disk(value)
+    }
+}
+
+fn name_bytes(name: &str) -> Result<[u8; MAX_NAME_LEN], FileSystemError> {
+    let src = name.as_bytes();
+    if src.is_empty() || src.len() > MAX_NAME_LEN {
+        // An overlong name can never match a stored entry, so report it as not found.
+        let mut padded = [0; MAX_NAME_LEN];
+        let copy_len = src.len().min(MAX_NAME_LEN);
+        padded[..copy_len].copy_from_slice(&src[..copy_len]);
+        return Err(FileSystemError::FileNotFound(padded));
+    }
+    let mut padded = [0; MAX_NAME_LEN];
+    padded[..src.len()].copy_from_slice(src);
+    Ok(padded)
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum OpenMode {
+    Read,
+    Append,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct OpenFile<const MAX_FILE_BLOCKS: usize> {
+    inode: u8,
+    mode: OpenMode,
+    offset: usize,
+}
+
+/// A handle to a file opened with [`FileSystem::open_read`] or [`FileSystem::open_append`].
+///
+/// The index it wraps is only meaningful to the [`FileSystem`] that created it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct FileDescriptor(usize);
+
+/// A single-directory (flat) file system laid out entirely in fixed-size blocks of an
+/// underlying [`RamDisk`], so that it stays `no_std`.
+///
+/// Block 0 is a superblock, followed by a free-block bitmap, a single block of inodes,
+/// and finally the data blocks. Each inode stores a file length and up to
+/// `MAX_FILE_BLOCKS` data block numbers, so each block number must fit in a `u8` and
+/// `NUM_BLOCKS` must not exceed 256. The root directory is itself a file, made up of
+/// fixed-width `(name, inode#)` entries, stored using the same block layout as any
+/// other file.
+#[derive(Copy, Clone, Debug)]
+pub struct FileSystem<
+    const MAX_OPEN: usize,
+    const BLOCK_SIZE: usize,
+    const NUM_BLOCKS: usize,
+    const MAX_FILE_BLOCKS: usize,
+> {
+    disk: RamDisk<BLOCK_SIZE, NUM_BLOCKS>,
+    open_files: [Option<OpenFile<MAX_FILE_BLOCKS>>; MAX_OPEN],
+}
+
+impl<
+        const MAX_OPEN: usize,
+        const BLOCK_SIZE: usize,
+        const NUM_BLOCKS: usize,
+        const MAX_FILE_BLOCKS: usize,
+    > FileSystem<MAX_OPEN, BLOCK_SIZE, NUM_BLOCKS, MAX_FILE_BLOCKS>
+{
+    const _NUM_BLOCKS_FITS_U8: () = assert!(
+        NUM_BLOCKS <= 256,
+        "NUM_BLOCKS must be <= 256 so that block numbers fit in a u8"
+    );
+
+    const INODE_SIZE: usize = INODE_LEN_OFFSET + 2 + MAX_FILE_BLOCKS;
+    const INODES_PER_BLOCK: usize = BLOCK_SIZE / Self::INODE_SIZE;
+    const BITMAP_START: usize = 1;
+    const BITMAP_BLOCKS: usize = NUM_BLOCKS.div_ceil(BLOCK_SIZE * 8);
+    const INODE_START: usize = Self::BITMAP_START + Self::BITMAP_BLOCKS;
+    const INODE_BLOCKS: usize = 1;
+    const DATA_START: usize = Self::INODE_START + Self::INODE_BLOCKS;
+    const MAX_FILES: usize = Self::INODES_PER_BLOCK;
+    const ROOT_INODE: u8 = 0;
+    const DIR_ENTRY_SIZE: usize = MAX_NAME_LEN + 1;
+
+    /// Formats a fresh, empty file system over a new, zeroed [`RamDisk`], reserving the
+    /// superblock, free bitmap, and inode blocks and creating an empty root directory.
+    pub fn new() -> Self {
+        let () = Self::_NUM_BLOCKS_FITS_U8;
+        let mut fs = Self {
+            disk: RamDisk::new(),
+            open_files: [None; MAX_OPEN],
+        };
+        for block in 0..Self::DATA_START {
+            fs.mark_used(block as u8);
+        }
+        fs.write_inode(Self::ROOT_INODE, true, 0, &[0; MAX_FILE_BLOCKS])
+            .expect("formatting a fresh file system cannot fail");
+        fs
+    }
+
+    fn bitmap_bit(block: u8) -> (usize, u8) {
+        let block = block as usize;
+        let byte = Self::BITMAP_START * BLOCK_SIZE + block / 8;
+        (byte, 1 << (block % 8))
+    }
+
+    fn mark_used(&mut self, block: u8) {
+        let (byte, mask) = Self::bitmap_bit(block);
+        let block_num = byte / BLOCK_SIZE;
+        let mut buf = [0; BLOCK_SIZE];
+        self.disk.read(block_num, &mut buf).expect("bitmap block is always in range");
+        buf[byte % BLOCK_SIZE] |= mask;
+        self.disk.write(block_num, &buf).expect("bitmap block is always in range");
+    }
+
+    fn is_used(&self, block: u8) -> bool {
+        let (byte, mask) = Self::bitmap_bit(block);
+        let block_num = byte / BLOCK_SIZE;
+        let mut buf = [0; BLOCK_SIZE];
+        self.disk.read(block_num, &mut buf).expect("bitmap block is always in range");
+        buf[byte % BLOCK_SIZE] & mask != 0
+    }
+
+    fn allocate_block(&mut self) -> Result<u8, FileSystemError> {
+        for block in Self::DATA_START..NUM_BLOCKS {
+            let block = block as u8;
+            if !self.is_used(block) {
+                self.mark_used(block);
+                return Ok(block);
+            }
+        }
+        Err(FileSystemError::DiskFull)
+    }
+
+    fn inode_location(inode: u8) -> (usize, usize) {
+        let inode = inode as usize;
+        let offset = inode * Self::INODE_SIZE;
+        (Self::INODE_START + offset / BLOCK_SIZE, offset % BLOCK_SIZE)
+    }
+
+    fn write_inode(
+        &mut self,
+        inode: u8,
+        used: bool,
+        len: u16,
+        blocks: &[u8; MAX_FILE_BLOCKS],
+    ) -> Result<(), RamDiskError> {
+        let (block_num, offset) = Self::inode_location(inode);
+        let mut buf = [0; BLOCK_SIZE];
+        self.disk.read(block_num, &mut buf)?;
+        buf[offset + INODE_USED_OFFSET] = used as u8;
+        buf[offset + INODE_LEN_OFFSET..offset + INODE_LEN_OFFSET + 2]
+            .copy_from_slice(&len.to_le_bytes());
+        buf[offset + INODE_LEN_OFFSET + 2..offset + Self::INODE_SIZE].copy_from_slice(blocks);
+        self.disk.write(block_num, &buf)
+    }
+
+    fn read_inode(&self, inode: u8) -> Result<(bool, u16, [u8; MAX_FILE_BLOCKS]), RamDiskError> {
+        let (block_num, offset) = Self::inode_location(inode);
+        let mut buf = [0; BLOCK_SIZE];
+        self.disk.read(block_num, &mut buf)?;
+        let used = buf[offset + INODE_USED_OFFSET] != 0;
+        let len = u16::from_le_bytes([
+            buf[offset + INODE_LEN_OFFSET],
+            buf[offset + INODE_LEN_OFFSET + 1],
+        ]);
+        let mut blocks = [0; MAX_FILE_BLOCKS];
+        blocks.copy_from_slice(&buf[offset + INODE_LEN_OFFSET + 2..offset + Self::INODE_SIZE]);
+        Ok((used, len, blocks))
+    }
+
+    fn allocate_inode(&mut self) -> Result<u8, FileSystemError> {
+        for inode in 0..Self::MAX_FILES as u8 {
+            let (used, _, _) = self.read_inode(inode)?;
+            if !used {
+                return Ok(inode);
+            }
+        }
+        Err(FileSystemError::TooManyFiles)
+    }
+
+    /// Appends `data` to whatever file is identified by `inode`, allocating new blocks
+    /// from the free-block bitmap as needed.
+    fn append_to_inode(
+        &mut self,
+        inode: u8,
+        data: &[u8],
+    ) -> Result<(), FileSystemError> {
+        let (used, len, mut blocks) = self.read_inode(inode)?;
+        let mut len = len as usize;
+        let mut block_index = len / BLOCK_SIZE;
+        let mut in_block_offset = len % BLOCK_SIZE;
+        for &byte in data {
+            if block_index >= MAX_FILE_BLOCKS {
+                return Err(FileSystemError::FileTooBig(MAX_FILE_BLOCKS));
+            }
+            if in_block_offset == 0 && blocks[block_index] == 0 {
+                blocks[block_index] = self.allocate_block()?;
+            }
+            let block_num = blocks[block_index] as usize;
+            let mut buf = [0; BLOCK_SIZE];
+            self.disk.read(block_num, &mut buf)?;
+            buf[in_block_offset] = byte;
+            self.disk.write(block_num, &buf)?;
+            len += 1;
+            in_block_offset += 1;
+            if in_block_offset == BLOCK_SIZE {
+                in_block_offset = 0;
+                block_index += 1;
+            }
+        }
+        self.write_inode(inode, used, len as u16, &blocks)?;
+        Ok(())
+    }
+
+    fn find_in_root(&self, name: &[u8; MAX_NAME_LEN]) -> Result<Option<u8>, FileSystemError> {
+        let (_used, len, blocks) = self.read_inode(Self::ROOT_INODE)?;
+        let mut remaining = len as usize;
+        let mut buf = [0; BLOCK_SIZE];
+        let mut entry = [0; MAX_NAME_LEN];
+        let mut entry_filled = 0;
+        for &block in blocks.iter() {
+            if remaining == 0 {
+                break;
+            }
+            if block == 0 {
+                break;
+            }
+            self.disk.read(block as usize, &mut buf)?;
+            let take = remaining.min(BLOCK_SIZE);
+            for &byte in &buf[..take] {
+                if entry_filled < MAX_NAME_LEN {
+                    entry[entry_filled] = byte;
+                } else {
+                    if &entry == name {
+                        return Ok(Some(byte));
+                    }
+                }
+                entry_filled += 1;
+                if entry_filled == Self::DIR_ENTRY_SIZE {
+                    entry_filled = 0;
+                }
+            }
+            remaining -= take;
+        }
+        Ok(None)
+    }
+
+    /// Creates a new, empty file named `name` in the root directory.
+    pub fn create(&mut self, name: &str) -> Result<(), FileSystemError> {
+        let name = name_bytes(name)?;
+        if self.find_in_root(&name)?.is_some() {
+            return Err(FileSystemError::FileAlreadyExists(name));
+        }
+        let inode = self.allocate_inode()?;
+        self.write_inode(inode, true, 0, &[0; MAX_FILE_BLOCKS])?;
+        let mut entry = [0; MAX_NAME_LEN + 1];
+        entry[..MAX_NAME_LEN].copy_from_slice(&name);
+        entry[MAX_NAME_LEN] = inode;
+        self.append_to_inode(Self::ROOT_INODE, &entry)?;
+        Ok(())
+    }
+
+    fn open(&mut self, name: &str, mode: OpenMode) -> Result<FileDescriptor, FileSystemError> {
+        let name_bytes = name_bytes(name)?;
+        let inode = self
+            .find_in_root(&name_bytes)?
+            .ok_or(FileSystemError::FileNotFound(name_bytes))?;
+        let slot = self
+            .open_files
+            .iter()
+            .position(|f| f.is_none())
+            .ok_or(FileSystemError::TooManyOpenFiles)?;
+        let offset = match mode {
+            OpenMode::Read => 0,
+            OpenMode::Append => {
+                let (_, len, _) = self.read_inode(inode)?;
+                len as usize
+            }
+        };
+        self.open_files[slot] = Some(OpenFile {
+            inode,
+            mode,
+            offset,
+        });
+        Ok(FileDescriptor(slot))
+    }
+
+    /// Opens an existing file for reading from the beginning.
+    pub fn open_read(&mut self, name: &str) -> Result<FileDescriptor, FileSystemError> {
+        self.open(name, OpenMode::Read)
+    }
+
+    /// Opens an existing file for appending, positioned at its current end.
+    pub fn open_append(&mut self, name: &str) -> Result<FileDescriptor, FileSystemError> {
+        self.open(name, OpenMode::Append)
+    }
+
+    fn open_file(&self, fd: FileDescriptor) -> Result<OpenFile<MAX_FILE_BLOCKS>, FileSystemError> {
+        self.open_files
+            .get(fd.0)
+            .copied()
+            .flatten()
+            .ok_or(FileSystemError::NotOpen(fd.0))
+    }
+
+    /// Reads up to `buffer.len()` bytes starting at the descriptor's current offset,
+    /// returning the number of bytes actually read (0 at end of file).
+    pub fn read(&mut self, fd: FileDescriptor, buffer: &mut [u8]) -> Result<usize, FileSystemError> {
+        let open_file = self.open_file(fd)?;
+        let (_, len, blocks) = self.read_inode(open_file.inode)?;
+        let len = len as usize;
+        let mut offset = open_file.offset;
+        let mut written = 0;
+        let mut buf = [0; BLOCK_SIZE];
+        while written < buffer.len() && offset < len {
+            let block_index = offset / BLOCK_SIZE;
+            let in_block_offset = offset % BLOCK_SIZE;
+            self.disk.read(blocks[block_index] as usize, &mut buf)?;
+            buffer[written] = buf[in_block_offset];
+            written += 1;
+            offset += 1;
+        }
+        self.open_files[fd.0].as_mut().unwrap().offset = offset;
+        Ok(written)
+    }
+
+    /// Appends `data` to the file referenced by `fd`, which must have been opened with
+    /// [`FileSystem::open_append`].
+    pub fn write(&mut self, fd: FileDescriptor, data: &[u8]) -> Result<(), FileSystemError> {
+        let open_file = self.open_file(fd)?;
+        if open_file.mode != OpenMode::Append {
+            return Err(FileSystemError::NotOpenForAppend(fd.0));
+        }
+        self.append_to_inode(open_file.inode, data)?;
+        self.open_files[fd.0].as_mut().unwrap().offset += data.len();
+        Ok(())
+    }
+
+    /// Closes a previously opened file descriptor, freeing its slot.
+    pub fn close(&mut self, fd: FileDescriptor) -> Result<(), FileSystemError> {
+        self.open_file(fd)?;
+        self.open_files[fd.0] = None;
+        Ok(())
+    }
+}
+
+impl<
+        const MAX_OPEN: usize,
+        const BLOCK_SIZE: usize,
+        const NUM_BLOCKS: usize,
+        const MAX_FILE_BLOCKS: usize,
+    > Default for FileSystem<MAX_OPEN, BLOCK_SIZE, NUM_BLOCKS, MAX_FILE_BLOCKS>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestFs = FileSystem<4, 32, 64, 4>;
+
+    #[test]
+    fn create_write_read() {
+        let mut fs = TestFs::new();
+        fs.create("hello.txt").unwrap();
+        let fd = fs.open_append("hello.txt").unwrap();
+        fs.write(fd, b"Hello, file system!").unwrap();
+        fs.close(fd).unwrap();
+
+        let fd = fs.open_read("hello.txt").unwrap();
+        let mut buf = [0; 64];
+        let read = fs.read(fd, &mut buf).unwrap();
+        fs.close(fd).unwrap();
+        assert_eq!(&buf[..read], b"Hello, file system!");
+    }
+
+    #[test]
+    fn missing_file_is_reported() {
+        let mut fs = TestFs::new();
+        assert!(matches!(
+            fs.open_read("missing.txt"),
+            Err(FileSystemError::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn duplicate_create_is_rejected() {
+        let mut fs = TestFs::new();
+        fs.create("a").unwrap();
+        assert!(matches!(
+            fs.create("a"),
+            Err(FileSystemError::FileAlreadyExists(_))
+        ));
+    }
+
+    #[test]
+    fn too_many_open_files_is_reported() {
+        let mut fs = TestFs::new();
+        fs.create("a").unwrap();
+        for _ in 0..4 {
+            fs.open_read("a").unwrap();
+        }
+        assert!(matches!(
+            fs.open_read("a"),
+            Err(FileSystemError::TooManyOpenFiles)
+        ));
+    }
+}
@@ -0,0 +1,422 @@
+use crate::{RamDisk, RamDiskError};
+use thiserror_no_std::Error;
+
+const GPT_SIGNATURE: [u8; 8] = *b"EFI PART";
+const MBR_BOOT_SIGNATURE: [u8; 2] = [0x55, 0xAA];
+const GPT_HEADER_LBA: usize = 1;
+const PARTITION_NAME_LEN: usize = 72;
+const PARTITION_ENTRY_SIZE: usize = 128;
+// The UEFI spec's minimum partition array size, and the count almost every GPT-writing
+// tool defaults to, regardless of how many entries are actually in use. Capping below
+// this would reject real-world GPT images outright.
+const MAX_PARTITIONS: usize = 128;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Error)]
+pub enum GptError {
+    #[error("Block size {0} is too small to hold a GPT partition entry")]
+    BlockTooSmall(usize),
+    #[error("LBA 0 does not contain a valid protective MBR")]
+    InvalidProtectiveMbr,
+    #[error("The GPT header signature is invalid")]
+    InvalidSignature,
+    #[error("The GPT header size is invalid or its CRC32 does not match its contents")]
+    HeaderCrcMismatch,
+    #[error("The partition entry size is unsupported or the partition array CRC32 does not match its contents")]
+    PartitionArrayCrcMismatch,
+    #[error("There are more than {0} partition entries")]
+    TooManyPartitions(usize),
+    #[error("Access at block {0} is outside the partition's [{1}, {2}] range")]
+    OutOfRange(usize, u64, u64),
+    #[error("Partition range [{0}, {1}] is not valid for a disk of {2} blocks")]
+    InvalidPartitionRange(u64, u64, usize),
+    #[error("{0}")]
+    Disk(RamDiskError),
+}
+
+impl core::error::Error for GptError {}
+
+impl From<RamDiskError> for GptError {
+    fn from(value: RamDiskError) -> Self {
+        Self::Disk(value)
+    }
+}
+
+/// A parsed, validated GPT partition entry.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PartitionEntry {
+    pub type_guid: [u8; 16],
+    pub unique_guid: [u8; 16],
+    pub first_lba: u64,
+    pub last_lba: u64,
+    pub name: [u16; PARTITION_NAME_LEN / 2],
+}
+
+impl PartitionEntry {
+    fn from_bytes(bytes: &[u8; PARTITION_ENTRY_SIZE]) -> Self {
+        let mut type_guid = [0; 16];
+        type_guid.copy_from_slice(&bytes[0..16]);
+        let mut unique_guid = [0; 16];
+        unique_guid.copy_from_slice(&bytes[16..32]);
+        let first_lba = u64::from_le_bytes(bytes[32..40].try_into().unwrap());
+        let last_lba = u64::from_le_bytes(bytes[40..48].try_into().unwrap());
+        let mut name = [0u16; PARTITION_NAME_LEN / 2];
+        for (i, chunk) in bytes[56..56 + PARTITION_NAME_LEN].chunks_exact(2).enumerate() {
+            name[i] = u16::from_le_bytes([chunk[0], chunk[1]]);
+        }
+        Self {
+            type_guid,
+            unique_guid,
+            first_lba,
+            last_lba,
+            name,
+        }
+    }
+
+    fn is_unused(&self) -> bool {
+        self.type_guid == [0; 16]
+    }
+}
+
+impl<const BLOCK_SIZE: usize, const NUM_BLOCKS: usize> RamDisk<BLOCK_SIZE, NUM_BLOCKS> {
+    /// Parses and validates the protective MBR and GPT header stored on this disk,
+    /// returning the partition entries that are actually in use.
+    ///
+    /// Both the header's own CRC32 and the partition array's CRC32 are recomputed and
+    /// checked; a mismatch in either is reported rather than trusted.
+    pub fn partitions(
+        &self,
+    ) -> Result<PartitionList<MAX_PARTITIONS>, GptError> {
+        if BLOCK_SIZE < PARTITION_ENTRY_SIZE {
+            return Err(GptError::BlockTooSmall(BLOCK_SIZE));
+        }
+
+        let mut mbr = [0; BLOCK_SIZE];
+        self.read(0, &mut mbr)?;
+        if mbr[BLOCK_SIZE - 2..BLOCK_SIZE] != MBR_BOOT_SIGNATURE {
+            return Err(GptError::InvalidProtectiveMbr);
+        }
+
+        let mut header = [0; BLOCK_SIZE];
+        self.read(GPT_HEADER_LBA, &mut header)?;
+        if header[0..8] != GPT_SIGNATURE {
+            return Err(GptError::InvalidSignature);
+        }
+
+        let header_size = u32::from_le_bytes(header[12..16].try_into().unwrap()) as usize;
+        let stored_header_crc = u32::from_le_bytes(header[16..20].try_into().unwrap());
+        if !(92..=BLOCK_SIZE).contains(&header_size) {
+            return Err(GptError::HeaderCrcMismatch);
+        }
+        let mut crc_checked = header;
+        crc_checked[16..20].copy_from_slice(&[0; 4]);
+        if crate::crc32::compute(&crc_checked[..header_size]) != stored_header_crc {
+            return Err(GptError::HeaderCrcMismatch);
+        }
+
+        let partition_array_lba = u64::from_le_bytes(header[72..80].try_into().unwrap());
+        let num_entries = u32::from_le_bytes(header[80..84].try_into().unwrap()) as usize;
+        let entry_size = u32::from_le_bytes(header[84..88].try_into().unwrap()) as usize;
+        let stored_array_crc = u32::from_le_bytes(header[88..92].try_into().unwrap());
+
+        if num_entries > MAX_PARTITIONS {
+            return Err(GptError::TooManyPartitions(MAX_PARTITIONS));
+        }
+        if entry_size != PARTITION_ENTRY_SIZE {
+            return Err(GptError::PartitionArrayCrcMismatch);
+        }
+
+        let entries_per_block = BLOCK_SIZE / PARTITION_ENTRY_SIZE;
+        let mut array_crc = 0xFFFF_FFFFu32;
+        let mut result = PartitionList::new();
+        let mut block_buf = [0; BLOCK_SIZE];
+        for entry_index in 0..num_entries {
+            let block_in_array = entry_index / entries_per_block;
+            let offset_in_block = (entry_index % entries_per_block) * PARTITION_ENTRY_SIZE;
+            if offset_in_block == 0 {
+                self.read(partition_array_lba as usize + block_in_array, &mut block_buf)?;
+            }
+            let entry_bytes: [u8; PARTITION_ENTRY_SIZE] = block_buf
+                [offset_in_block..offset_in_block + PARTITION_ENTRY_SIZE]
+                .try_into()
+                .unwrap();
+            array_crc = crate::crc32::update(array_crc, &entry_bytes);
+
+            let entry = PartitionEntry::from_bytes(&entry_bytes);
+            if !entry.is_unused() {
+                result.push(entry);
+            }
+        }
+        if !array_crc != stored_array_crc {
+            return Err(GptError::PartitionArrayCrcMismatch);
+        }
+
+        Ok(result)
+    }
+}
+
+/// A fixed-capacity partition list, since this crate is `no_std` and cannot use `Vec`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PartitionList<const MAX: usize> {
+    entries: [Option<PartitionEntry>; MAX],
+    len: usize,
+}
+
+impl<const MAX: usize> PartitionList<MAX> {
+    fn new() -> Self {
+        Self {
+            entries: [None; MAX],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, entry: PartitionEntry) {
+        if self.len < MAX {
+            self.entries[self.len] = Some(entry);
+            self.len += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PartitionEntry> {
+        self.entries[..self.len].iter().filter_map(|e| e.as_ref())
+    }
+}
+
+/// A view over a [`RamDisk`] that clamps reads and writes to the LBA range
+/// `[first_lba, last_lba]` of a single partition, remapping block indices to be
+/// relative to the partition's start, the way a real block device partition works.
+pub struct Partition<'a, const BLOCK_SIZE: usize, const NUM_BLOCKS: usize> {
+    disk: &'a mut RamDisk<BLOCK_SIZE, NUM_BLOCKS>,
+    first_lba: u64,
+    last_lba: u64,
+}
+
+impl<'a, const BLOCK_SIZE: usize, const NUM_BLOCKS: usize> Partition<'a, BLOCK_SIZE, NUM_BLOCKS> {
+    pub fn new(
+        disk: &'a mut RamDisk<BLOCK_SIZE, NUM_BLOCKS>,
+        entry: &PartitionEntry,
+    ) -> Result<Self, GptError> {
+        if entry.first_lba > entry.last_lba || entry.last_lba >= NUM_BLOCKS as u64 {
+            return Err(GptError::InvalidPartitionRange(
+                entry.first_lba,
+                entry.last_lba,
+                NUM_BLOCKS,
+            ));
+        }
+        Ok(Self {
+            disk,
+            first_lba: entry.first_lba,
+            last_lba: entry.last_lba,
+        })
+    }
+
+    fn absolute_block(&self, block: usize) -> Result<usize, GptError> {
+        let absolute = self.first_lba + block as u64;
+        if absolute > self.last_lba {
+            Err(GptError::OutOfRange(block, self.first_lba, self.last_lba))
+        } else {
+            Ok(absolute as usize)
+        }
+    }
+
+    pub fn read(&self, block: usize, buffer: &mut [u8; BLOCK_SIZE]) -> Result<(), GptError> {
+        let absolute = self.absolute_block(block)?;
+        self.disk.read(absolute, buffer)?;
+        Ok(())
+    }
+
+    pub fn write(&mut self, block: usize, buffer: &[u8; BLOCK_SIZE]) -> Result<(), GptError> {
+        let absolute = self.absolute_block(block)?;
+        self.disk.write(absolute, buffer)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK_SIZE: usize = 128;
+    const NUM_BLOCKS: usize = 16;
+
+    fn write_test_gpt(disk: &mut RamDisk<BLOCK_SIZE, NUM_BLOCKS>) {
+        let mut mbr = [0; BLOCK_SIZE];
+        mbr[BLOCK_SIZE - 2] = 0x55;
+        mbr[BLOCK_SIZE - 1] = 0xAA;
+        disk.write(0, &mbr).unwrap();
+
+        let array_lba = 2u64;
+        let mut entry_bytes = [0u8; PARTITION_ENTRY_SIZE];
+        entry_bytes[0] = 1;
+        entry_bytes[32..40].copy_from_slice(&4u64.to_le_bytes());
+        entry_bytes[40..48].copy_from_slice(&7u64.to_le_bytes());
+
+        let mut array_block = [0; BLOCK_SIZE];
+        array_block[..PARTITION_ENTRY_SIZE].copy_from_slice(&entry_bytes);
+        let array_crc = crate::crc32::compute(&array_block[..PARTITION_ENTRY_SIZE]);
+        disk.write(array_lba as usize, &array_block).unwrap();
+
+        let mut header = [0; BLOCK_SIZE];
+        header[0..8].copy_from_slice(&GPT_SIGNATURE);
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[72..80].copy_from_slice(&array_lba.to_le_bytes());
+        header[80..84].copy_from_slice(&1u32.to_le_bytes());
+        header[84..88].copy_from_slice(&(PARTITION_ENTRY_SIZE as u32).to_le_bytes());
+        header[88..92].copy_from_slice(&array_crc.to_le_bytes());
+        let header_crc = crate::crc32::compute(&header[..92]);
+        header[16..20].copy_from_slice(&header_crc.to_le_bytes());
+        disk.write(GPT_HEADER_LBA, &header).unwrap();
+    }
+
+    #[test]
+    fn parses_single_partition() {
+        let mut disk = RamDisk::<BLOCK_SIZE, NUM_BLOCKS>::new();
+        write_test_gpt(&mut disk);
+
+        let partitions = disk.partitions().unwrap();
+        assert_eq!(partitions.len(), 1);
+        let entry = partitions.iter().next().unwrap();
+        assert_eq!(entry.first_lba, 4);
+        assert_eq!(entry.last_lba, 7);
+    }
+
+    #[test]
+    fn rejects_corrupted_header_crc() {
+        let mut disk = RamDisk::<BLOCK_SIZE, NUM_BLOCKS>::new();
+        write_test_gpt(&mut disk);
+
+        let mut header = [0; BLOCK_SIZE];
+        disk.read(GPT_HEADER_LBA, &mut header).unwrap();
+        header[20] ^= 0xFF;
+        disk.write(GPT_HEADER_LBA, &header).unwrap();
+
+        assert_eq!(disk.partitions(), Err(GptError::HeaderCrcMismatch));
+    }
+
+    #[test]
+    fn oversized_header_size_is_rejected_not_panicking() {
+        let mut disk = RamDisk::<BLOCK_SIZE, NUM_BLOCKS>::new();
+        write_test_gpt(&mut disk);
+
+        let mut header = [0; BLOCK_SIZE];
+        disk.read(GPT_HEADER_LBA, &mut header).unwrap();
+        header[12..16].copy_from_slice(&200u32.to_le_bytes());
+        disk.write(GPT_HEADER_LBA, &header).unwrap();
+
+        assert_eq!(disk.partitions(), Err(GptError::HeaderCrcMismatch));
+    }
+
+    #[test]
+    fn block_size_too_small_for_one_entry_is_rejected() {
+        const SMALL_BLOCK_SIZE: usize = 100;
+        let disk = RamDisk::<SMALL_BLOCK_SIZE, NUM_BLOCKS>::new();
+        assert_eq!(
+            disk.partitions(),
+            Err(GptError::BlockTooSmall(SMALL_BLOCK_SIZE))
+        );
+    }
+
+    #[test]
+    fn standard_gpt_declared_entry_count_is_accepted() {
+        const STANDARD_NUM_BLOCKS: usize = 140;
+        let mut disk = RamDisk::<BLOCK_SIZE, STANDARD_NUM_BLOCKS>::new();
+
+        let mut mbr = [0; BLOCK_SIZE];
+        mbr[BLOCK_SIZE - 2] = 0x55;
+        mbr[BLOCK_SIZE - 1] = 0xAA;
+        disk.write(0, &mbr).unwrap();
+
+        let array_lba = 2u64;
+        let entries_per_block = BLOCK_SIZE / PARTITION_ENTRY_SIZE;
+        let num_entries = 128u32;
+        let array_blocks = (num_entries as usize).div_ceil(entries_per_block);
+
+        let mut entry_bytes = [0u8; PARTITION_ENTRY_SIZE];
+        entry_bytes[0] = 1;
+        entry_bytes[32..40].copy_from_slice(&4u64.to_le_bytes());
+        entry_bytes[40..48].copy_from_slice(&7u64.to_le_bytes());
+
+        let mut array_crc = 0xFFFF_FFFFu32;
+        let mut block_buf = [0u8; BLOCK_SIZE];
+        block_buf[..PARTITION_ENTRY_SIZE].copy_from_slice(&entry_bytes);
+        disk.write(array_lba as usize, &block_buf).unwrap();
+        array_crc = crate::crc32::update(array_crc, &entry_bytes);
+        for _ in 1..num_entries {
+            array_crc = crate::crc32::update(array_crc, &[0; PARTITION_ENTRY_SIZE]);
+        }
+        for block in 1..array_blocks {
+            disk.write(array_lba as usize + block, &[0; BLOCK_SIZE])
+                .unwrap();
+        }
+        let array_crc = !array_crc;
+
+        let mut header = [0; BLOCK_SIZE];
+        header[0..8].copy_from_slice(&GPT_SIGNATURE);
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[72..80].copy_from_slice(&array_lba.to_le_bytes());
+        header[80..84].copy_from_slice(&num_entries.to_le_bytes());
+        header[84..88].copy_from_slice(&(PARTITION_ENTRY_SIZE as u32).to_le_bytes());
+        header[88..92].copy_from_slice(&array_crc.to_le_bytes());
+        let header_crc = crate::crc32::compute(&header[..92]);
+        header[16..20].copy_from_slice(&header_crc.to_le_bytes());
+        disk.write(GPT_HEADER_LBA, &header).unwrap();
+
+        let partitions = disk.partitions().unwrap();
+        assert_eq!(partitions.len(), 1);
+        let entry = partitions.iter().next().unwrap();
+        assert_eq!(entry.first_lba, 4);
+        assert_eq!(entry.last_lba, 7);
+    }
+
+    #[test]
+    fn partition_with_invalid_range_is_rejected() {
+        let mut disk = RamDisk::<BLOCK_SIZE, NUM_BLOCKS>::new();
+        let backwards = PartitionEntry {
+            type_guid: [1; 16],
+            unique_guid: [0; 16],
+            first_lba: 7,
+            last_lba: 4,
+            name: [0; PARTITION_NAME_LEN / 2],
+        };
+        assert_eq!(
+            Partition::new(&mut disk, &backwards).err(),
+            Some(GptError::InvalidPartitionRange(7, 4, NUM_BLOCKS))
+        );
+
+        let out_of_bounds = PartitionEntry {
+            last_lba: NUM_BLOCKS as u64,
+            ..backwards
+        };
+        assert_eq!(
+            Partition::new(&mut disk, &out_of_bounds).err(),
+            Some(GptError::InvalidPartitionRange(
+                7,
+                NUM_BLOCKS as u64,
+                NUM_BLOCKS
+            ))
+        );
+    }
+
+    #[test]
+    fn partition_view_clamps_access() {
+        let mut disk = RamDisk::<BLOCK_SIZE, NUM_BLOCKS>::new();
+        write_test_gpt(&mut disk);
+        let entry = *disk.partitions().unwrap().iter().next().unwrap();
+        let mut partition = Partition::new(&mut disk, &entry).unwrap();
+
+        let block = [42; BLOCK_SIZE];
+        partition.write(0, &block).unwrap();
+        let mut read_back = [0; BLOCK_SIZE];
+        partition.read(0, &mut read_back).unwrap();
+        assert_eq!(read_back, block);
+
+        assert!(partition.read(4, &mut read_back).is_err());
+    }
+}
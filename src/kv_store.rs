@@ -0,0 +1,397 @@
+use crate::crc32;
+use crate::{BlockDevice, DiskCursor};
+use core::fmt::Debug;
+use thiserror_no_std::Error;
+
+const HEADER_LEN: usize = 2 + 2;
+const CRC_LEN: usize = 4;
+const TOMBSTONE_VAL_LEN: u16 = u16::MAX;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Error)]
+pub enum KvError<E> {
+    #[error("No entry with the given key exists")]
+    KeyNotFound,
+    #[error("The log has no room left, even after compaction")]
+    OutOfSpace,
+    #[error("A record's checksum did not match its contents")]
+    Corruption,
+    #[error("A key or value is longer than this store's fixed capacity")]
+    TooLarge,
+    #[error("{0:?}")]
+    Disk(E),
+}
+
+impl<E: Debug> core::error::Error for KvError<E> {}
+
+impl<E> From<E> for KvError<E> {
+    fn from(value: E) -> Self {
+        Self::Disk(value)
+    }
+}
+
+/// A log-structured, append-only key-value store layered over a [`BlockDevice`], similar
+/// to a simple flash configuration store (for example NVS on ESP-IDF).
+///
+/// Each write appends a record `[key_len][val_len][key][value][crc32]`; the newest
+/// record for a key always wins, so `get` scans for the last matching record and `set`
+/// just appends. The device is split into two equally sized regions; once the active
+/// region fills up, [`KvStore::compact`] copies only the live (most recent, non-removed)
+/// records into the other region and switches to it, which spreads writes evenly across
+/// the whole device for crude wear leveling.
+///
+/// `MAX_KEY_LEN` and `MAX_VAL_LEN` bound a single key/value pair, since this crate has no
+/// allocator to size them dynamically.
+pub struct KvStore<
+    const BLOCK_SIZE: usize,
+    const NUM_BLOCKS: usize,
+    const MAX_KEY_LEN: usize,
+    const MAX_VAL_LEN: usize,
+    D: BlockDevice,
+> {
+    cursor: DiskCursor<BLOCK_SIZE, D>,
+    region: u8,
+    write_ptr: u64,
+}
+
+impl<
+        const BLOCK_SIZE: usize,
+        const NUM_BLOCKS: usize,
+        const MAX_KEY_LEN: usize,
+        const MAX_VAL_LEN: usize,
+        D: BlockDevice<Error = E>,
+        E: Debug,
+    > KvStore<BLOCK_SIZE, NUM_BLOCKS, MAX_KEY_LEN, MAX_VAL_LEN, D>
+{
+    const _NUM_BLOCKS_HAS_TWO_REGIONS: () =
+        assert!(NUM_BLOCKS >= 2, "NUM_BLOCKS must be at least 2 to form two log regions");
+
+    const REGION_BLOCKS: usize = NUM_BLOCKS / 2;
+    const REGION_BYTES: u64 = (Self::REGION_BLOCKS * BLOCK_SIZE) as u64;
+
+    /// Formats a fresh, empty store over `device`.
+    pub fn new(device: D) -> Self {
+        let () = Self::_NUM_BLOCKS_HAS_TWO_REGIONS;
+        Self {
+            cursor: DiskCursor::new(device),
+            region: 0,
+            write_ptr: 0,
+        }
+    }
+
+    fn region_base(&self, region: u8) -> u64 {
+        region as u64 * Self::REGION_BYTES
+    }
+
+    fn read_at(&mut self, region: u8, offset: u64, buffer: &mut [u8]) -> Result<usize, E> {
+        self.cursor.seek(self.region_base(region) + offset);
+        self.cursor.read(buffer)
+    }
+
+    fn write_at(&mut self, region: u8, offset: u64, buffer: &[u8]) -> Result<(), E> {
+        self.cursor.seek(self.region_base(region) + offset);
+        self.cursor.write(buffer)?;
+        Ok(())
+    }
+
+    /// Reads the header and key of the record at `offset` in `region`, returning
+    /// `(key_len, val_len, record_len)` plus the key bytes written into `key_buf`.
+    /// Returns `Ok(None)` at a zeroed (never-written) header, signaling end of log.
+    fn read_record_header(
+        &mut self,
+        region: u8,
+        offset: u64,
+        key_buf: &mut [u8; MAX_KEY_LEN],
+    ) -> Result<Option<(usize, u16, usize)>, KvError<E>> {
+        let mut header = [0; HEADER_LEN];
+        if self.read_at(region, offset, &mut header)? < HEADER_LEN {
+            return Ok(None);
+        }
+        let key_len = u16::from_le_bytes([header[0], header[1]]) as usize;
+        let val_len = u16::from_le_bytes([header[2], header[3]]);
+        if key_len == 0 && val_len == 0 {
+            return Ok(None);
+        }
+        if key_len > MAX_KEY_LEN {
+            return Err(KvError::Corruption);
+        }
+        let value_len = if val_len == TOMBSTONE_VAL_LEN {
+            0
+        } else {
+            val_len as usize
+        };
+        if value_len > MAX_VAL_LEN {
+            return Err(KvError::Corruption);
+        }
+        self.read_at(region, offset + HEADER_LEN as u64, &mut key_buf[..key_len])?;
+        let record_len = HEADER_LEN + key_len + value_len + CRC_LEN;
+        Ok(Some((key_len, val_len, record_len)))
+    }
+
+    fn verify_record_crc(
+        &mut self,
+        region: u8,
+        offset: u64,
+        record_len: usize,
+    ) -> Result<bool, KvError<E>> {
+        let mut buf = [0u8; 8];
+        let mut crc = 0xFFFF_FFFFu32;
+        let mut read = 0;
+        let payload_len = record_len - CRC_LEN;
+        while read < payload_len {
+            let chunk_len = buf.len().min(payload_len - read);
+            self.read_at(region, offset + read as u64, &mut buf[..chunk_len])?;
+            crc = crc32::update(crc, &buf[..chunk_len]);
+            read += chunk_len;
+        }
+        let crc = !crc;
+        let mut stored_crc = [0; CRC_LEN];
+        self.read_at(region, offset + payload_len as u64, &mut stored_crc)?;
+        Ok(crc == u32::from_le_bytes(stored_crc))
+    }
+
+    /// Scans the active region for the last (newest) record matching `key`, validating
+    /// each record's checksum and stopping at the first truncated or zeroed record.
+    fn find_last(
+        &mut self,
+        key: &[u8],
+    ) -> Result<Option<(u64, u16, usize)>, KvError<E>> {
+        if key.len() > MAX_KEY_LEN {
+            return Err(KvError::TooLarge);
+        }
+        let mut offset = 0;
+        let mut found = None;
+        let mut key_buf = [0; MAX_KEY_LEN];
+        while offset < self.write_ptr {
+            let Some((key_len, val_len, record_len)) =
+                self.read_record_header(self.region, offset, &mut key_buf)?
+            else {
+                break;
+            };
+            if !self.verify_record_crc(self.region, offset, record_len)? {
+                return Err(KvError::Corruption);
+            }
+            if &key_buf[..key_len] == key {
+                found = Some((offset, val_len, record_len));
+            }
+            offset += record_len as u64;
+        }
+        Ok(found)
+    }
+
+    /// Reads the value stored under `key` into `buffer`, returning the number of bytes
+    /// written.
+    pub fn get(&mut self, key: &[u8], buffer: &mut [u8]) -> Result<usize, KvError<E>> {
+        match self.find_last(key)? {
+            Some((offset, val_len, _)) if val_len != TOMBSTONE_VAL_LEN => {
+                let val_len = val_len as usize;
+                self.read_at(
+                    self.region,
+                    offset + (HEADER_LEN + key.len()) as u64,
+                    &mut buffer[..val_len],
+                )?;
+                Ok(val_len)
+            }
+            _ => Err(KvError::KeyNotFound),
+        }
+    }
+
+    fn append_record(&mut self, key: &[u8], value: &[u8], val_len_field: u16) -> Result<(), KvError<E>> {
+        let record_len = HEADER_LEN + key.len() + value.len() + CRC_LEN;
+        if Self::REGION_BYTES - self.write_ptr < record_len as u64 {
+            self.compact()?;
+            if Self::REGION_BYTES - self.write_ptr < record_len as u64 {
+                return Err(KvError::OutOfSpace);
+            }
+        }
+
+        let mut header = [0; HEADER_LEN];
+        header[0..2].copy_from_slice(&(key.len() as u16).to_le_bytes());
+        header[2..4].copy_from_slice(&val_len_field.to_le_bytes());
+
+        let mut crc = crc32::update(0xFFFF_FFFF, &header);
+        crc = crc32::update(crc, key);
+        crc = crc32::update(crc, value);
+        let crc = !crc;
+
+        let offset = self.write_ptr;
+        self.write_at(self.region, offset, &header)?;
+        self.write_at(self.region, offset + HEADER_LEN as u64, key)?;
+        self.write_at(self.region, offset + (HEADER_LEN + key.len()) as u64, value)?;
+        self.write_at(
+            self.region,
+            offset + (HEADER_LEN + key.len() + value.len()) as u64,
+            &crc.to_le_bytes(),
+        )?;
+        self.write_ptr += record_len as u64;
+        Ok(())
+    }
+
+    /// Appends a record setting `key` to `value`. The previous value, if any, is left in
+    /// the log but is shadowed: [`KvStore::get`] always returns the newest record.
+    pub fn set(&mut self, key: &[u8], value: &[u8]) -> Result<(), KvError<E>> {
+        if key.len() > MAX_KEY_LEN
+            || value.len() > MAX_VAL_LEN
+            || value.len() >= TOMBSTONE_VAL_LEN as usize
+        {
+            return Err(KvError::TooLarge);
+        }
+        self.append_record(key, value, value.len() as u16)
+    }
+
+    /// Appends a tombstone record, so that subsequent [`KvStore::get`] calls report
+    /// `key` as not found.
+    pub fn remove(&mut self, key: &[u8]) -> Result<(), KvError<E>> {
+        if key.len() > MAX_KEY_LEN {
+            return Err(KvError::TooLarge);
+        }
+        self.append_record(key, &[], TOMBSTONE_VAL_LEN)
+    }
+
+    fn exists_later_duplicate(
+        &mut self,
+        from: u64,
+        key_len: usize,
+        key: &[u8; MAX_KEY_LEN],
+    ) -> Result<bool, KvError<E>> {
+        let mut offset = from;
+        let mut scan_key = [0; MAX_KEY_LEN];
+        while offset < self.write_ptr {
+            let Some((scan_key_len, _, record_len)) =
+                self.read_record_header(self.region, offset, &mut scan_key)?
+            else {
+                break;
+            };
+            if scan_key_len == key_len && scan_key[..key_len] == key[..key_len] {
+                return Ok(true);
+            }
+            offset += record_len as u64;
+        }
+        Ok(false)
+    }
+
+    /// Copies only the newest, non-removed record for every key into the other region,
+    /// then switches to it, reclaiming the space occupied by shadowed and removed
+    /// records. Spreading the live records across whichever region is currently idle is
+    /// what gives this store its crude wear leveling.
+    pub fn compact(&mut self) -> Result<(), KvError<E>> {
+        let source = self.region;
+        let dest = 1 - source;
+        let mut scan_offset = 0;
+        let mut dest_offset = 0u64;
+        let mut key_buf = [0; MAX_KEY_LEN];
+        let mut record_buf = [0u8; HEADER_LEN];
+
+        while scan_offset < self.write_ptr {
+            let Some((key_len, val_len, record_len)) =
+                self.read_record_header(source, scan_offset, &mut key_buf)?
+            else {
+                break;
+            };
+            let is_tombstone = val_len == TOMBSTONE_VAL_LEN;
+            let superseded = !is_tombstone
+                && self.exists_later_duplicate(scan_offset + record_len as u64, key_len, &key_buf)?;
+
+            if !is_tombstone && !superseded {
+                let val_len = val_len as usize;
+                let key = {
+                    let mut key = [0; MAX_KEY_LEN];
+                    key[..key_len].copy_from_slice(&key_buf[..key_len]);
+                    key
+                };
+                let mut value = [0u8; MAX_VAL_LEN];
+                self.read_at(
+                    source,
+                    scan_offset + (HEADER_LEN + key_len) as u64,
+                    &mut value[..val_len],
+                )?;
+
+                record_buf[0..2].copy_from_slice(&(key_len as u16).to_le_bytes());
+                record_buf[2..4].copy_from_slice(&(val_len as u16).to_le_bytes());
+                let mut crc = crc32::update(0xFFFF_FFFF, &record_buf);
+                crc = crc32::update(crc, &key[..key_len]);
+                crc = crc32::update(crc, &value[..val_len]);
+                let crc = !crc;
+
+                self.write_at(dest, dest_offset, &record_buf)?;
+                self.write_at(dest, dest_offset + HEADER_LEN as u64, &key[..key_len])?;
+                self.write_at(
+                    dest,
+                    dest_offset + (HEADER_LEN + key_len) as u64,
+                    &value[..val_len],
+                )?;
+                self.write_at(
+                    dest,
+                    dest_offset + (HEADER_LEN + key_len + val_len) as u64,
+                    &crc.to_le_bytes(),
+                )?;
+                dest_offset += (HEADER_LEN + key_len + val_len + CRC_LEN) as u64;
+            }
+            scan_offset += record_len as u64;
+        }
+
+        self.region = dest;
+        self.write_ptr = dest_offset;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RamDisk;
+
+    type TestStore = KvStore<16, 4, 8, 8, RamDisk<16, 4>>;
+
+    #[test]
+    fn set_then_get_round_trips() {
+        let mut store = TestStore::new(RamDisk::new());
+        store.set(b"a", b"1").unwrap();
+        store.set(b"b", b"22").unwrap();
+
+        let mut buf = [0; 8];
+        let len = store.get(b"a", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"1");
+        let len = store.get(b"b", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"22");
+    }
+
+    #[test]
+    fn newest_value_wins() {
+        let mut store = TestStore::new(RamDisk::new());
+        store.set(b"a", b"1").unwrap();
+        store.set(b"a", b"2").unwrap();
+
+        let mut buf = [0; 8];
+        let len = store.get(b"a", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"2");
+    }
+
+    #[test]
+    fn removed_key_is_not_found() {
+        let mut store = TestStore::new(RamDisk::new());
+        store.set(b"a", b"1").unwrap();
+        store.remove(b"a").unwrap();
+
+        let mut buf = [0; 8];
+        assert_eq!(store.get(b"a", &mut buf), Err(KvError::KeyNotFound));
+    }
+
+    #[test]
+    fn value_length_colliding_with_tombstone_sentinel_is_rejected() {
+        type LimitStore = KvStore<16, 4, 8, { u16::MAX as usize }, RamDisk<16, 4>>;
+        let mut store = LimitStore::new(RamDisk::new());
+        let value = [7u8; u16::MAX as usize];
+        assert_eq!(store.set(b"k", &value), Err(KvError::TooLarge));
+    }
+
+    #[test]
+    fn compaction_reclaims_shadowed_records_and_preserves_latest() {
+        let mut store = TestStore::new(RamDisk::new());
+        for i in 0..40u8 {
+            store.set(b"a", &[i]).unwrap();
+        }
+        let mut buf = [0; 8];
+        let len = store.get(b"a", &mut buf).unwrap();
+        assert_eq!(&buf[..len], &[39]);
+    }
+}
@@ -2,6 +2,20 @@
 use thiserror_no_std::Error;
 use core::result::Result;
 
+mod block_device;
+mod crc32;
+mod file_system;
+mod gpt;
+mod kv_store;
+mod sparse_disk;
+mod storage;
+pub use block_device::{BlockDevice, DiskCursor};
+pub use file_system::{FileDescriptor, FileSystem, FileSystemError};
+pub use gpt::{GptError, Partition, PartitionEntry, PartitionList};
+pub use kv_store::{KvError, KvStore};
+pub use sparse_disk::{BlockStatus, Snapshot, SparseDisk, SparseDiskError};
+pub use storage::{CheckedStorage, CheckedStorageError, Storage};
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Error)]
 pub enum RamDiskError {
     #[error("Attempt to read block {0}; maximum block {1}")]
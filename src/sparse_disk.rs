@@ -0,0 +1,236 @@
+use thiserror_no_std::Error;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Error)]
+pub enum SparseDiskError {
+    #[error("Attempt to read block {0}; maximum block {1}")]
+    IllegalBlockRead(usize, usize),
+    #[error("Attempt to write block {0}; maximum block {1}")]
+    IllegalBlockWrite(usize, usize),
+    #[error("The backing pool of {0} blocks has no free slots remaining")]
+    PoolExhausted(usize),
+}
+
+impl core::error::Error for SparseDiskError {}
+
+/// Whether a logical block currently has storage backing it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BlockStatus {
+    /// The block has been written at least once since the disk (or its most recent
+    /// copy-on-write ancestor) was created, and occupies a slot in the backing pool.
+    Allocated,
+    /// The block has never been written; reading it returns zeros, and it occupies no
+    /// space in the backing pool.
+    Sparse,
+}
+
+/// A frozen, read-only view of a [`SparseDisk`]'s block mapping at the moment
+/// [`SparseDisk::snapshot`] was called.
+///
+/// Taking a snapshot does not copy any block contents: it only remembers, for every
+/// logical block, which backing-pool slot (if any) held its data at that instant. Later
+/// writes to the live disk that would disturb a slot a snapshot depends on are
+/// redirected to a fresh slot instead, so the snapshot keeps reading the original data.
+#[derive(Copy, Clone, Debug)]
+pub struct Snapshot<const NUM_BLOCKS: usize> {
+    block_slots: [Option<u16>; NUM_BLOCKS],
+}
+
+/// A sparse, copy-on-write block store: logical blocks are only given backing storage
+/// the first time they are written, so a large, mostly-empty disk costs little.
+///
+/// `POOL_SIZE` bounds the number of blocks that can ever be written across the disk's
+/// lifetime, counting every copy-on-write fork created by [`SparseDisk::snapshot`]; once
+/// exhausted, further writes to previously-shared blocks fail.
+#[derive(Copy, Clone, Debug)]
+pub struct SparseDisk<const BLOCK_SIZE: usize, const NUM_BLOCKS: usize, const POOL_SIZE: usize> {
+    block_slots: [Option<u16>; NUM_BLOCKS],
+    slot_epoch: [u32; POOL_SIZE],
+    pool: [[u8; BLOCK_SIZE]; POOL_SIZE],
+    pool_len: usize,
+    epoch: u32,
+}
+
+impl<const BLOCK_SIZE: usize, const NUM_BLOCKS: usize, const POOL_SIZE: usize>
+    SparseDisk<BLOCK_SIZE, NUM_BLOCKS, POOL_SIZE>
+{
+    pub fn new() -> Self {
+        Self {
+            block_slots: [None; NUM_BLOCKS],
+            slot_epoch: [0; POOL_SIZE],
+            pool: [[0; BLOCK_SIZE]; POOL_SIZE],
+            pool_len: 0,
+            epoch: 0,
+        }
+    }
+
+    pub fn num_blocks(&self) -> usize {
+        NUM_BLOCKS
+    }
+
+    pub fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    /// Reads the current contents of `block`; a never-written (sparse) block reads as
+    /// all zeros.
+    pub fn read(
+        &self,
+        block: usize,
+        buffer: &mut [u8; BLOCK_SIZE],
+    ) -> Result<(), SparseDiskError> {
+        Self::read_mapping(&self.pool, &self.block_slots, block, buffer)
+    }
+
+    /// Writes `buffer` to `block`, allocating a fresh pool slot if the block is
+    /// currently sparse, or if any [`Snapshot`] has been taken since the block was given
+    /// its current slot, whether or not that snapshot is still held.
+    pub fn write(
+        &mut self,
+        block: usize,
+        buffer: &[u8; BLOCK_SIZE],
+    ) -> Result<(), SparseDiskError> {
+        if block >= NUM_BLOCKS {
+            return Err(SparseDiskError::IllegalBlockWrite(block, NUM_BLOCKS - 1));
+        }
+        let private_slot = match self.block_slots[block] {
+            Some(slot) if self.slot_epoch[slot as usize] == self.epoch => Some(slot),
+            _ => None,
+        };
+        let slot = match private_slot {
+            Some(slot) => slot,
+            None => {
+                let slot = self.allocate_slot()?;
+                self.block_slots[block] = Some(slot);
+                self.slot_epoch[slot as usize] = self.epoch;
+                slot
+            }
+        };
+        self.pool[slot as usize] = *buffer;
+        Ok(())
+    }
+
+    fn allocate_slot(&mut self) -> Result<u16, SparseDiskError> {
+        if self.pool_len >= POOL_SIZE {
+            return Err(SparseDiskError::PoolExhausted(POOL_SIZE));
+        }
+        let slot = self.pool_len as u16;
+        self.pool_len += 1;
+        Ok(slot)
+    }
+
+    /// Freezes the disk's current block mapping and returns a handle to it. Writes made
+    /// to the live disk after this call never affect what the snapshot reads.
+    pub fn snapshot(&mut self) -> Snapshot<NUM_BLOCKS> {
+        let snapshot = Snapshot {
+            block_slots: self.block_slots,
+        };
+        self.epoch += 1;
+        snapshot
+    }
+
+    /// Reads `block` as it was at the moment `snapshot` was taken.
+    pub fn read_snapshot(
+        &self,
+        snapshot: &Snapshot<NUM_BLOCKS>,
+        block: usize,
+        buffer: &mut [u8; BLOCK_SIZE],
+    ) -> Result<(), SparseDiskError> {
+        Self::read_mapping(&self.pool, &snapshot.block_slots, block, buffer)
+    }
+
+    fn read_mapping(
+        pool: &[[u8; BLOCK_SIZE]; POOL_SIZE],
+        block_slots: &[Option<u16>; NUM_BLOCKS],
+        block: usize,
+        buffer: &mut [u8; BLOCK_SIZE],
+    ) -> Result<(), SparseDiskError> {
+        match block_slots.get(block) {
+            Some(Some(slot)) => {
+                *buffer = pool[*slot as usize];
+                Ok(())
+            }
+            Some(None) => {
+                *buffer = [0; BLOCK_SIZE];
+                Ok(())
+            }
+            None => Err(SparseDiskError::IllegalBlockRead(block, NUM_BLOCKS - 1)),
+        }
+    }
+
+    /// Whether `block` currently occupies a slot in the backing pool.
+    pub fn status(&self, block: usize) -> BlockStatus {
+        match self.block_slots.get(block) {
+            Some(Some(_)) => BlockStatus::Allocated,
+            _ => BlockStatus::Sparse,
+        }
+    }
+
+    /// Iterates the logical block numbers that are currently allocated, in order,
+    /// skipping sparse runs entirely. A compact serialization only needs to store these
+    /// blocks' contents plus their block numbers.
+    pub fn allocated_blocks(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..NUM_BLOCKS).filter(move |&block| self.status(block) == BlockStatus::Allocated)
+    }
+}
+
+impl<const BLOCK_SIZE: usize, const NUM_BLOCKS: usize, const POOL_SIZE: usize> Default
+    for SparseDisk<BLOCK_SIZE, NUM_BLOCKS, POOL_SIZE>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparse_blocks_read_as_zero() {
+        let disk = SparseDisk::<4, 8, 4>::new();
+        let mut buf = [1; 4];
+        disk.read(0, &mut buf).unwrap();
+        assert_eq!(buf, [0; 4]);
+        assert_eq!(disk.status(0), BlockStatus::Sparse);
+    }
+
+    #[test]
+    fn write_allocates_a_pool_slot() {
+        let mut disk = SparseDisk::<4, 8, 4>::new();
+        disk.write(0, &[1, 2, 3, 4]).unwrap();
+        let mut buf = [0; 4];
+        disk.read(0, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
+        assert_eq!(disk.status(0), BlockStatus::Allocated);
+        assert_eq!(disk.allocated_blocks().collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn snapshot_is_unaffected_by_later_writes() {
+        let mut disk = SparseDisk::<4, 8, 4>::new();
+        disk.write(0, &[1, 1, 1, 1]).unwrap();
+        let snapshot = disk.snapshot();
+        disk.write(0, &[2, 2, 2, 2]).unwrap();
+
+        let mut live = [0; 4];
+        disk.read(0, &mut live).unwrap();
+        assert_eq!(live, [2, 2, 2, 2]);
+
+        let mut frozen = [0; 4];
+        disk.read_snapshot(&snapshot, 0, &mut frozen).unwrap();
+        assert_eq!(frozen, [1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn pool_exhaustion_is_reported() {
+        let mut disk = SparseDisk::<4, 8, 2>::new();
+        disk.write(0, &[0; 4]).unwrap();
+        let _snapshot = disk.snapshot();
+        disk.write(1, &[0; 4]).unwrap();
+        let _snapshot2 = disk.snapshot();
+        assert_eq!(
+            disk.write(0, &[9; 4]),
+            Err(SparseDiskError::PoolExhausted(2))
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{BlockDevice, RamDisk};
+use core::fmt::Debug;
+use thiserror_no_std::Error;
+
+/// A [`BlockDevice`] that also models flash-style program/erase constraints, so code
+/// written against this trait exercises the same invariants it would hit on real
+/// NOR/NAND media.
+pub trait Storage: BlockDevice {
+    /// The smallest unit that can be read, in bytes.
+    const READ_SIZE: usize;
+    /// The smallest unit that can be programmed (written), in bytes.
+    const WRITE_SIZE: usize;
+    /// The number of erase cycles a block is rated to endure before it may become
+    /// unreliable.
+    const BLOCK_CYCLES: u32;
+    /// The byte value a block reads as immediately after being erased.
+    const ERASE_BYTE: u8 = 0xFF;
+
+    /// Resets `block` to [`Storage::ERASE_BYTE`], the way a flash erase operation would.
+    fn erase(&mut self, block: usize) -> Result<(), Self::Error>;
+}
+
+impl<const BLOCK_SIZE: usize, const NUM_BLOCKS: usize> Storage
+    for RamDisk<BLOCK_SIZE, NUM_BLOCKS>
+{
+    const READ_SIZE: usize = 1;
+    const WRITE_SIZE: usize = 1;
+    // Plain memory has no meaningful wear limit, unlike the flash media this trait
+    // otherwise models.
+    const BLOCK_CYCLES: u32 = u32::MAX;
+
+    fn erase(&mut self, block: usize) -> Result<(), Self::Error> {
+        let erased = [Self::ERASE_BYTE; BLOCK_SIZE];
+        BlockDevice::write(self, block, &erased)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Error)]
+pub enum CheckedStorageError<E> {
+    #[error("Block {0} must be erased before it can be written again")]
+    NotErased(usize),
+    #[error("Block {0} is out of range for this device")]
+    OutOfRange(usize),
+    #[error("{0:?}")]
+    Disk(E),
+}
+
+impl<E: Debug> core::error::Error for CheckedStorageError<E> {}
+
+/// Wraps a [`Storage`] and enforces the program-after-erase rule that real flash media
+/// requires: a block must be erased before every write, including its first write through
+/// this wrapper, and a write without a preceding erase is rejected with
+/// [`CheckedStorageError::NotErased`].
+///
+/// `NUM_BLOCKS` must match the wrapped device's own block count, since it sizes this
+/// wrapper's per-block erase bookkeeping.
+pub struct CheckedStorage<const NUM_BLOCKS: usize, S: Storage> {
+    inner: S,
+    erased_since_write: [bool; NUM_BLOCKS],
+    erase_counts: [u32; NUM_BLOCKS],
+}
+
+impl<const NUM_BLOCKS: usize, S: Storage> CheckedStorage<NUM_BLOCKS, S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            erased_since_write: [false; NUM_BLOCKS],
+            erase_counts: [0; NUM_BLOCKS],
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// How many times `block` has been erased, so tests can assert on wear
+    /// distribution across the device.
+    pub fn erase_count(&self, block: usize) -> u32 {
+        self.erase_counts.get(block).copied().unwrap_or(0)
+    }
+
+    pub fn read(&self, block: usize, buffer: &mut [u8]) -> Result<(), S::Error> {
+        self.inner.read(block, buffer)
+    }
+
+    pub fn erase(&mut self, block: usize) -> Result<(), CheckedStorageError<S::Error>> {
+        if block >= self.erased_since_write.len() {
+            return Err(CheckedStorageError::OutOfRange(block));
+        }
+        self.inner
+            .erase(block)
+            .map_err(CheckedStorageError::Disk)?;
+        self.erased_since_write[block] = true;
+        self.erase_counts[block] += 1;
+        Ok(())
+    }
+
+    /// Writes `buffer` to `block`, which must have been erased since its last write (or,
+    /// if it has never been written through this wrapper, erased at least once).
+    pub fn write(
+        &mut self,
+        block: usize,
+        buffer: &[u8],
+    ) -> Result<(), CheckedStorageError<S::Error>> {
+        let erased = *self
+            .erased_since_write
+            .get(block)
+            .ok_or(CheckedStorageError::OutOfRange(block))?;
+        if !erased {
+            return Err(CheckedStorageError::NotErased(block));
+        }
+        self.inner
+            .write(block, buffer)
+            .map_err(CheckedStorageError::Disk)?;
+        self.erased_since_write[block] = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RamDisk;
+
+    #[test]
+    fn erase_sets_the_erase_byte() {
+        let mut disk = RamDisk::<4, 2>::new();
+        disk.write(0, &[1, 2, 3, 4]).unwrap();
+        Storage::erase(&mut disk, 0).unwrap();
+        let mut buf = [0; 4];
+        disk.read(0, &mut buf).unwrap();
+        assert_eq!(buf, [0xFF; 4]);
+    }
+
+    #[test]
+    fn write_without_erase_is_rejected() {
+        let mut storage = CheckedStorage::<2, _>::new(RamDisk::<4, 2>::new());
+        assert_eq!(
+            storage.write(0, &[1, 2, 3, 4]),
+            Err(CheckedStorageError::NotErased(0))
+        );
+    }
+
+    #[test]
+    fn write_after_erase_succeeds_once() {
+        let mut storage = CheckedStorage::<2, _>::new(RamDisk::<4, 2>::new());
+        storage.erase(0).unwrap();
+        storage.write(0, &[1, 2, 3, 4]).unwrap();
+        let mut buf = [0; 4];
+        storage.read(0, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
+
+        assert_eq!(
+            storage.write(0, &[5, 6, 7, 8]),
+            Err(CheckedStorageError::NotErased(0))
+        );
+    }
+
+    #[test]
+    fn erase_out_of_range_is_rejected_not_panicking() {
+        let mut storage = CheckedStorage::<2, _>::new(RamDisk::<4, 8>::new());
+        assert_eq!(storage.erase(5), Err(CheckedStorageError::OutOfRange(5)));
+    }
+
+    #[test]
+    fn erase_counts_track_wear() {
+        let mut storage = CheckedStorage::<2, _>::new(RamDisk::<4, 2>::new());
+        for _ in 0..3 {
+            storage.erase(0).unwrap();
+            storage.write(0, &[0; 4]).unwrap();
+        }
+        assert_eq!(storage.erase_count(0), 3);
+        assert_eq!(storage.erase_count(1), 0);
+    }
+}